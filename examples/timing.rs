@@ -59,6 +59,9 @@ pub fn main() {
         std::cmp::max(3, num_cpus::get()) - 2,
         &[0xdb; 20],
         &[0xdb; 32],
+        spike_work_proof::WorkParams::default(),
+        None,
+        None,
     )
     .unwrap();
 
@@ -69,7 +72,7 @@ pub fn main() {
             loop {
                 for _ in 0..10 {
                     let start = std::time::Instant::now();
-                    let dif = iter.next().unwrap();
+                    let dif = iter.next().unwrap().to_f64();
                     let dur = start.elapsed().as_secs_f64();
                     dur_dif.push((dur, dif));
                 }
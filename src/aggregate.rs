@@ -0,0 +1,383 @@
+//! K-of-N aggregate proofs with cheap, probabilistic subset verification.
+//!
+//! A single proof meeting a high [Difficulty] costs exponentially more
+//! hashes to find the higher that difficulty goes. [AggregateProof] takes
+//! the opposite approach: find `N` independent search values that each
+//! clear a modest per-solution difficulty `d` (distinguished by their
+//! 4-byte node field), and let a verifier check only a random `k`-subset
+//! of them instead of recomputing all `N` Argon2 hashes.
+
+use crate::{Difficulty, WorkParams, WorkProof};
+
+/// Algorithm id used in the encoded aggregate proof header. Distinct from
+/// [params](crate::params)'s single-proof `ALGO_ARGON2ID` so the two
+/// encodings can never be confused for one another.
+const ALGO_ARGON2ID_AGGREGATE: u8 = 2;
+
+/// Encoding format version, independent of the Argon2 version byte.
+const FORMAT_VERSION: u8 = 1;
+
+/// Length of one solution value (see the crate-level docs on [WorkProof]).
+const VALUE_LEN: usize = 20;
+
+/// `[algo_id][format_version][argon2_version][output_len][mem_kib:4]`
+/// `[iterations:4][lanes:4][d:8][n:4][salt:32]`, followed by `n` solution
+/// values.
+const HEADER_LEN: usize = 1 + 1 + 1 + 1 + 4 + 4 + 4 + 8 + 4 + 32;
+
+/// Largest `n` [AggregateProof::decode] will accept from an untrusted
+/// proof, so a malicious proof can't force a verifier to allocate an
+/// unbounded `values` vector before [AggregateProof::verify] ever gets to
+/// its own k-subset bound.
+const MAX_DECODED_N: u32 = 1 << 20;
+
+/// An aggregate proof: `N` independent search values against the same
+/// salt, each claimed to clear a per-solution difficulty `d`.
+pub struct AggregateProof {
+    salt: [u8; 32],
+    params: WorkParams,
+    secret: Option<Vec<u8>>,
+    associated_data: Option<Vec<u8>>,
+    d: Difficulty,
+    values: Vec<[u8; VALUE_LEN]>,
+}
+
+impl AggregateProof {
+    /// Generate an [AggregateProof] of `n` independent solutions against
+    /// `hash`, each clearing difficulty `d`, seeded from `seed`. This is
+    /// equivalent to finding `n` separate [WorkProof]s, one per node id
+    /// `0..n`, each to difficulty `d`.
+    pub fn generate(
+        seed: &[u8],
+        hash: &[u8],
+        params: WorkParams,
+        secret: Option<&[u8]>,
+        associated_data: Option<&[u8]>,
+        n: u32,
+        d: Difficulty,
+    ) -> crate::Result<Self> {
+        if hash.len() != 32 {
+            return Err("hash must be 32 bytes".into());
+        }
+        let salt: [u8; 32] = hash.try_into().unwrap();
+        let target = d.to_target();
+
+        let mut values = Vec::with_capacity(n as usize);
+        for node in 0..n {
+            // one WorkProof per node id, searched to difficulty `d`.
+            let mut wp = WorkProof::init(
+                1,
+                seed,
+                hash,
+                params,
+                secret,
+                associated_data,
+            )?
+            .remove(0);
+            wp.set_node(node);
+            wp.refresh()?;
+
+            while !wp.meets_target(&target) {
+                wp.next()?;
+            }
+
+            let pwd: [u8; VALUE_LEN] = wp.proof_bytes();
+            values.push(pwd);
+        }
+
+        Ok(AggregateProof {
+            salt,
+            params,
+            secret: secret.map(<[u8]>::to_vec),
+            associated_data: associated_data.map(<[u8]>::to_vec),
+            d,
+            values,
+        })
+    }
+
+    /// The number of independent solutions in this proof.
+    pub fn n(&self) -> u32 {
+        self.values.len() as u32
+    }
+
+    /// The claimed total work: `N * 10^d`.
+    pub fn claimed_work(&self) -> f64 {
+        self.values.len() as f64 * 10f64.powf(self.d.to_f64())
+    }
+
+    /// Encode this proof into a portable, self-describing byte string a
+    /// prover can hand to a verifier in another process (or over the
+    /// network) to pass to [Self::decode]. `secret` and `associated_data`
+    /// are deliberately not included -- like [WorkProof::verify], a
+    /// verifier must already hold them out of band.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.values.len() * VALUE_LEN);
+        out.push(ALGO_ARGON2ID_AGGREGATE);
+        out.push(FORMAT_VERSION);
+        out.push(self.params.version as u8);
+        out.push(crate::OUTPUT_LEN as u8);
+        out.extend_from_slice(&self.params.mem_kib.to_le_bytes());
+        out.extend_from_slice(&self.params.iterations.to_le_bytes());
+        out.extend_from_slice(&self.params.lanes.to_le_bytes());
+        out.extend_from_slice(&self.d.to_f64().to_le_bytes());
+        out.extend_from_slice(&(self.values.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.salt);
+        for value in &self.values {
+            out.extend_from_slice(value);
+        }
+        out
+    }
+
+    /// Decode an [Self::encode]d proof, pairing it back up with the
+    /// `secret` / `associated_data` (if any) it was generated with so
+    /// [Self::verify] can rebuild the exact Argon2 instance.
+    pub fn decode(
+        encoded: &[u8],
+        secret: Option<&[u8]>,
+        associated_data: Option<&[u8]>,
+    ) -> crate::Result<Self> {
+        if encoded.len() < HEADER_LEN {
+            return Err(format!(
+                "encoded aggregate proof must be at least {HEADER_LEN} bytes, got {}",
+                encoded.len()
+            ));
+        }
+        if encoded[0] != ALGO_ARGON2ID_AGGREGATE {
+            return Err(format!("unsupported algorithm id {}", encoded[0]));
+        }
+        if encoded[1] != FORMAT_VERSION {
+            return Err(format!("unsupported format version {}", encoded[1]));
+        }
+        let version = WorkParams::decode_version(encoded[2])?;
+        let output_len = encoded[3] as usize;
+        if output_len != crate::OUTPUT_LEN {
+            return Err(format!(
+                "unsupported output length {output_len}, expected {}",
+                crate::OUTPUT_LEN
+            ));
+        }
+        let mem_kib = u32::from_le_bytes(encoded[4..8].try_into().unwrap());
+        let iterations = u32::from_le_bytes(encoded[8..12].try_into().unwrap());
+        let lanes = u32::from_le_bytes(encoded[12..16].try_into().unwrap());
+        WorkParams::validate_decoded_costs(mem_kib, iterations, lanes)?;
+
+        let d = Difficulty::from_f64(f64::from_le_bytes(
+            encoded[16..24].try_into().unwrap(),
+        ));
+        let n = u32::from_le_bytes(encoded[24..28].try_into().unwrap());
+        // `n` came straight out of an untrusted proof too -- bound it
+        // before allocating `values`, for the same reason `mem_kib` et al.
+        // are bounded above.
+        if n == 0 || n > MAX_DECODED_N {
+            return Err(format!(
+                "n {n} out of accepted range (1..={MAX_DECODED_N})"
+            ));
+        }
+        let salt: [u8; 32] = encoded[28..HEADER_LEN].try_into().unwrap();
+
+        let expected_len = HEADER_LEN + n as usize * VALUE_LEN;
+        if encoded.len() != expected_len {
+            return Err(format!(
+                "encoded aggregate proof must be {expected_len} bytes for n={n}, got {}",
+                encoded.len()
+            ));
+        }
+
+        let mut values = Vec::with_capacity(n as usize);
+        for i in 0..n as usize {
+            let start = HEADER_LEN + i * VALUE_LEN;
+            let value: [u8; VALUE_LEN] =
+                encoded[start..start + VALUE_LEN].try_into().unwrap();
+            values.push(value);
+        }
+
+        Ok(AggregateProof {
+            salt,
+            params: WorkParams {
+                mem_kib,
+                iterations,
+                lanes,
+                version,
+            },
+            secret: secret.map(<[u8]>::to_vec),
+            associated_data: associated_data.map(<[u8]>::to_vec),
+            d,
+            values,
+        })
+    }
+
+    /// Verify this proof by recomputing Argon2 for a deterministic,
+    /// pseudo-random `k`-subset of the `N` solutions, accepting if every
+    /// sampled solution clears `d` and all `N` values are distinct. This
+    /// bounds verification cost at `k` Argon2 evaluations instead of `N`,
+    /// trading a `(fraction valid)^k` chance of accepting a partially
+    /// forged proof for that speedup.
+    ///
+    /// `nonce` must be unpredictable to the prover *before* the proof was
+    /// generated -- bytes from the verifier's own RNG, or a fresh
+    /// challenge value for a networked verifier. The subset is seeded
+    /// from `nonce`, not from the solution values themselves: those are
+    /// fully prover-controlled, so seeding from them would let a
+    /// dishonest prover grind cheap placeholder values (no Argon2
+    /// required) until the sampled subset happened to land only on its
+    /// genuine solutions, defeating the `(fraction valid)^k` bound above.
+    pub fn verify(&self, k: usize, nonce: &[u8]) -> crate::Result<bool> {
+        if self.values.iter().collect::<std::collections::HashSet<_>>().len()
+            != self.values.len()
+        {
+            return Ok(false);
+        }
+
+        let target = self.d.to_target();
+        for &index in self.sample_indices(k, nonce).iter() {
+            let pwd = self.values[index];
+            let (_difficulty, raw) = WorkProof::hash_raw(
+                &pwd,
+                &self.salt,
+                &self.params,
+                self.secret.as_deref(),
+                self.associated_data.as_deref(),
+            )?;
+            if !target.meets(raw) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Deterministically pick `k` of the `N` solution indices, seeded from
+    /// the verifier's `nonce` and this proof's salt -- never from the
+    /// solution values, which the prover fully controls (see
+    /// [Self::verify]).
+    fn sample_indices(&self, k: usize, nonce: &[u8]) -> Vec<usize> {
+        let k = k.min(self.values.len());
+
+        let mut hash_input = Vec::with_capacity(nonce.len() + self.salt.len());
+        hash_input.extend_from_slice(nonce);
+        hash_input.extend_from_slice(&self.salt);
+        let mut rng_state = fnv1a64(&hash_input);
+
+        // partial Fisher-Yates: shuffle just the first k slots.
+        let mut indices: Vec<usize> = (0..self.values.len()).collect();
+        for i in 0..k {
+            let remaining = indices.len() - i;
+            let pick = i + (splitmix64(&mut rng_state) as usize) % remaining;
+            indices.swap(i, pick);
+        }
+        indices.truncate(k);
+        indices
+    }
+}
+
+/// A small, non-cryptographic hash used only to seed the deterministic
+/// subset sampler above -- collision resistance isn't needed here, just a
+/// seed that the same nonce and salt always reproduce.
+fn fnv1a64(data: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// A standard SplitMix64 step, used to turn the FNV seed above into a
+/// stream of pseudo-random subset picks.
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_params() -> WorkParams {
+        WorkParams {
+            mem_kib: 8,
+            iterations: 1,
+            lanes: 1,
+            version: argon2::Version::V0x13,
+        }
+    }
+
+    #[test]
+    fn generate_and_verify_roundtrip() {
+        let proof = AggregateProof::generate(
+            &[1; 20],
+            &[2; 32],
+            test_params(),
+            None,
+            None,
+            5,
+            Difficulty::ZERO,
+        )
+        .unwrap();
+
+        assert_eq!(proof.n(), 5);
+        assert!(proof.verify(3, b"verifier-nonce").unwrap());
+    }
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let proof = AggregateProof::generate(
+            &[1; 20],
+            &[2; 32],
+            test_params(),
+            None,
+            None,
+            5,
+            Difficulty::ZERO,
+        )
+        .unwrap();
+
+        let encoded = proof.encode();
+        let decoded = AggregateProof::decode(&encoded, None, None).unwrap();
+
+        assert_eq!(decoded.n(), 5);
+        assert!(decoded.verify(3, b"verifier-nonce").unwrap());
+    }
+
+    #[test]
+    fn decode_rejects_oversized_n() {
+        let proof = AggregateProof::generate(
+            &[1; 20],
+            &[2; 32],
+            test_params(),
+            None,
+            None,
+            2,
+            Difficulty::ZERO,
+        )
+        .unwrap();
+
+        let mut encoded = proof.encode();
+        let bogus_n = (MAX_DECODED_N + 1).to_le_bytes();
+        encoded[24..28].copy_from_slice(&bogus_n);
+
+        assert!(AggregateProof::decode(&encoded, None, None).is_err());
+    }
+
+    #[test]
+    fn verify_rejects_duplicated_values() {
+        let mut proof = AggregateProof::generate(
+            &[1; 20],
+            &[2; 32],
+            test_params(),
+            None,
+            None,
+            5,
+            Difficulty::ZERO,
+        )
+        .unwrap();
+
+        let first = proof.values[0];
+        proof.values[1] = first;
+
+        assert!(!proof.verify(3, b"verifier-nonce").unwrap());
+    }
+}
@@ -0,0 +1,118 @@
+//! Calibrate [WorkParams] to the current machine instead of relying on
+//! fixed, hand-tuned cost constants.
+
+use crate::{WorkParams, WorkProof};
+use std::time::{Duration, Instant};
+
+/// A memory cost ceiling `calibrate` won't grow past, even if
+/// `target_verify` can't be met: 1 GiB.
+const MAX_MEM_KIB: u32 = 1 << 20;
+
+/// A throwaway hash / seed used only to benchmark hashing speed; the
+/// values themselves don't matter, only how long they take to process.
+const BENCH_HASH: [u8; 32] = [0xa5; 32];
+const BENCH_SEED: [u8; 20] = [0x5a; 20];
+
+impl WorkParams {
+    /// Benchmark this machine and return [WorkParams] tuned so that a
+    /// single [WorkProof::verify] takes roughly `target_verify`, and a
+    /// proof of difficulty 1.0 takes roughly `target_unit_difficulty_time`
+    /// of hashing to generate. Also returns the measured hashes-per-second
+    /// at the chosen parameters.
+    ///
+    /// Doubles `mem_kib` (starting from 1024), keeping the largest value
+    /// that still meets `target_verify`, then picks `iterations` so a
+    /// single hash takes `target_unit_difficulty_time / 10` -- difficulty
+    /// 1.0 corresponds to about 10 expected hash attempts (see the
+    /// crate-level docs).
+    ///
+    /// Since a bigger `mem_kib` only ever makes a single hash slower,
+    /// escalation stops the moment one candidate misses `target_verify`;
+    /// if even `mem_kib = 1024` can't meet it, returns `Err` rather than
+    /// silently handing back settings too expensive for the caller's
+    /// budget.
+    pub fn calibrate(
+        target_verify: Duration,
+        target_unit_difficulty_time: Duration,
+    ) -> crate::Result<(WorkParams, f64)> {
+        let mut mem_kib = 1024;
+        let mut best: Option<(u32, Duration)> = None;
+        loop {
+            let params = WorkParams {
+                mem_kib,
+                iterations: 1,
+                lanes: 1,
+                version: argon2::Version::V0x13,
+            };
+            let elapsed = Self::time_single_hash(&params)?;
+            if elapsed > target_verify {
+                // mem_kib only ever makes a hash slower, so once one
+                // candidate blows the budget there's no point paying for
+                // a bigger one -- stop and keep whatever last passed.
+                break;
+            }
+            best = Some((mem_kib, elapsed));
+            if mem_kib >= MAX_MEM_KIB {
+                break;
+            }
+            mem_kib = (mem_kib.saturating_mul(2)).min(MAX_MEM_KIB);
+        }
+
+        let (mem_kib, mut single_hash_time) = best.ok_or_else(|| {
+            format!(
+                "no mem_kib (starting at 1024) met target_verify of {target_verify:?}"
+            )
+        })?;
+
+        // difficulty 1.0 takes ~10 expected hash attempts, so a single
+        // hash should take about a tenth of the requested unit time.
+        let desired_hash_time = target_unit_difficulty_time.div_f64(10.0);
+        let iterations = (desired_hash_time.as_secs_f64()
+            / single_hash_time.as_secs_f64())
+        .round()
+        .max(1.0) as u32;
+
+        let params = WorkParams {
+            mem_kib,
+            iterations,
+            lanes: 1,
+            version: argon2::Version::V0x13,
+        };
+        single_hash_time = Self::time_single_hash(&params)?;
+        let hashes_per_sec = 1.0 / single_hash_time.as_secs_f64();
+
+        Ok((params, hashes_per_sec))
+    }
+
+    /// Time a single hash at `params` using the existing [WorkProof]
+    /// machinery.
+    fn time_single_hash(params: &WorkParams) -> crate::Result<Duration> {
+        let mut wp =
+            WorkProof::init(1, &BENCH_SEED, &BENCH_HASH, *params, None, None)?
+                .remove(0);
+        let start = Instant::now();
+        wp.next()?;
+        Ok(start.elapsed())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn calibrate_errors_when_budget_is_unreachable() {
+        let result =
+            WorkParams::calibrate(Duration::from_nanos(1), Duration::from_secs(1));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn calibrate_picks_params_within_budget() {
+        let budget = Duration::from_secs(2);
+        let (params, hashes_per_sec) =
+            WorkParams::calibrate(budget, Duration::from_millis(100)).unwrap();
+        assert!(WorkParams::time_single_hash(&params).unwrap() <= budget);
+        assert!(hashes_per_sec > 0.0);
+    }
+}
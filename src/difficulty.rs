@@ -0,0 +1,160 @@
+//! Difficulty and target types.
+//!
+//! A raw hash output is a `u128`; the closer it is to `u128::MAX`, the
+//! harder (read: less likely) it was to find. [Difficulty] is the
+//! human-facing log10 of that likelihood, and [Target] is the exact
+//! integer threshold an output must clear to meet a given [Difficulty] --
+//! so the hot comparison in [WorkProof::meets_target](crate::WorkProof::meets_target)
+//! never has to touch floating point.
+
+use std::cmp::Ordering;
+
+/// The largest [Difficulty] we bother representing. Beyond this the
+/// corresponding [Target] is within a handful of u128 units of
+/// `u128::MAX`, i.e. already astronomically unreachable.
+const MAX_DIFFICULTY: f64 = 38.0;
+
+/// A log10 difficulty, e.g. `1.0` means a single hash clears it with
+/// probability 1 in 10, so the proof took on average 10 hash attempts to
+/// reach (see the crate-level docs).
+///
+/// Always finite and clamped to `[0.0, 38.0]`, so unlike a bare `f64`
+/// it has a total order and saturating arithmetic.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difficulty(f64);
+
+impl Difficulty {
+    /// The zero difficulty -- any output meets it.
+    pub const ZERO: Self = Difficulty(0.0);
+
+    /// The maximum difficulty this type represents.
+    pub const MAX: Self = Difficulty(MAX_DIFFICULTY);
+
+    /// Construct a [Difficulty] from a log10 value, clamping it into
+    /// `[0.0, MAX]`. `NaN` becomes [Self::ZERO].
+    pub fn from_f64(value: f64) -> Self {
+        if value.is_nan() {
+            return Self::ZERO;
+        }
+        Difficulty(value.clamp(0.0, MAX_DIFFICULTY))
+    }
+
+    /// The underlying log10 value.
+    pub fn to_f64(self) -> f64 {
+        self.0
+    }
+
+    /// Add two difficulties, saturating at [Self::MAX].
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Difficulty((self.0 + rhs.0).min(MAX_DIFFICULTY))
+    }
+
+    /// Subtract two difficulties, saturating at [Self::ZERO].
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Difficulty((self.0 - rhs.0).max(0.0))
+    }
+
+    /// Convert to the exact integer [Target] threshold that an output
+    /// must meet or exceed to clear this difficulty: `floor(u128::MAX *
+    /// (1 - 10^-D))`.
+    ///
+    /// Computed as `u128::MAX - floor(u128::MAX * 10^-D)` rather than
+    /// `floor(u128::MAX * (1 - 10^-D))` directly: once `10^-D` drops
+    /// below `f64::EPSILON` (around `D > 16`), `1.0 - 10^-D` suffers
+    /// catastrophic cancellation and rounds to exactly `1.0`, silently
+    /// returning `u128::MAX` for every higher difficulty. The missed
+    /// slice, `u128::MAX * 10^-D`, stays a well-conditioned multiply
+    /// across the whole `[0, MAX]` range, and the subtraction from
+    /// `u128::MAX` happens in exact integer space.
+    pub fn to_target(self) -> Target {
+        let miss_fraction = 10f64.powf(-self.0);
+        let miss = (u128::MAX as f64 * miss_fraction) as u128;
+        Target(u128::MAX - miss)
+    }
+}
+
+impl Eq for Difficulty {}
+
+impl PartialOrd for Difficulty {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Difficulty {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `Difficulty` is always constructed finite via `from_f64`'s clamp,
+        // so a total order is safe here.
+        self.0.partial_cmp(&other.0).expect("Difficulty is always finite")
+    }
+}
+
+/// The exact integer threshold a raw hash output (as a little-endian
+/// `u128`) must meet or exceed to clear a [Difficulty].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Target(u128);
+
+impl Target {
+    /// Build a [Target] directly from a raw threshold value.
+    pub fn from_raw(threshold: u128) -> Self {
+        Target(threshold)
+    }
+
+    /// Does `out` meet or exceed this target?
+    pub fn meets(&self, out: u128) -> bool {
+        out >= self.0
+    }
+
+    /// Convert back to the human log10 [Difficulty] this target
+    /// corresponds to. Only needed on demand for display; the hot
+    /// acceptance check is [Self::meets].
+    ///
+    /// Computes the miss fraction `(u128::MAX - self.0) / u128::MAX`
+    /// directly (an exact integer subtraction first) rather than via
+    /// `1.0 - pct`, for the same cancellation reason as [Difficulty::to_target].
+    pub fn difficulty(&self) -> Difficulty {
+        let miss = u128::MAX - self.0;
+        let miss_fraction = miss as f64 / u128::MAX as f64;
+        Difficulty::from_f64(-miss_fraction.log10())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn high_difficulty_target_is_not_u128_max() {
+        // before the cancellation fix this returned exactly u128::MAX for
+        // every difficulty above ~16.
+        for d in [17.0, 20.0, 30.0, 38.0] {
+            let target = Difficulty::from_f64(d).to_target();
+            assert_ne!(
+                target,
+                Target::from_raw(u128::MAX),
+                "difficulty {d} collapsed to u128::MAX"
+            );
+        }
+    }
+
+    #[test]
+    fn higher_difficulty_has_stricter_target() {
+        let low = Difficulty::from_f64(17.0).to_target();
+        let high = Difficulty::from_f64(20.0).to_target();
+        assert!(high > low);
+    }
+
+    #[test]
+    fn zero_difficulty_meets_everything() {
+        let target = Difficulty::ZERO.to_target();
+        assert!(target.meets(0));
+        assert!(target.meets(u128::MAX));
+    }
+
+    #[test]
+    fn target_meets_is_inclusive_boundary() {
+        let target = Target::from_raw(100);
+        assert!(target.meets(100));
+        assert!(!target.meets(99));
+    }
+}
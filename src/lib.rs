@@ -39,38 +39,100 @@
 //! The argon2 parameters were chosen to require an amount of work to be done
 //! (the whole point of this excercise), while also making it not too onerous
 //! to validate the proofs on whatever process is doing the validation.
+//!
+//! ## Mining
+//!
+//! [WorkProof::next] is open-ended -- each call hashes one more candidate,
+//! which may be better or worse than the last, without ever telling you
+//! to stop. [Miner] wraps a pool of these in worker threads
+//! and stops them as soon as one clears a target difficulty, which is the
+//! loop most callers actually want.
+//!
+//! ## Keyed proofs
+//!
+//! Argon2 also accepts a secret key `K` and associated data `X` beyond
+//! the password and salt. Passing a `secret` to [WorkProof::init] and
+//! [WorkProof::verify] produces proofs that only a validator holding that
+//! secret can check, and passing `associated_data` binds a proof to a
+//! context (a domain string, an expiry) without touching the salt.
+//!
+//! ## Difficulty and targets
+//!
+//! [Difficulty] is the human log10 value from the docs above; [Target]
+//! is the exact integer threshold ([Difficulty::to_target]) a raw hash
+//! output must meet to clear it. [WorkProof::meets_target] compares
+//! against a [Target] directly, so the acceptance check in a hot mining
+//! loop never touches floating point.
+//!
+//! ## Aggregate proofs
+//!
+//! Reaching one proof at a high difficulty costs exponentially more
+//! hashes as difficulty rises. [AggregateProof] instead finds `N`
+//! independent solutions that each clear a modest difficulty, and lets a
+//! verifier recompute only a random `k`-subset of them -- bounding
+//! verification cost at `k` Argon2 evaluations instead of `N`.
+//!
+//! ## Calibration
+//!
+//! [WorkParams]'s memory/time costs are otherwise fixed constants, so the
+//! same settings cost wildly different amounts on different hardware.
+//! [WorkParams::calibrate] benchmarks this machine and picks settings to
+//! hit a target validation time and a target per-unit-difficulty
+//! generation time.
 
 #[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
+mod miner;
+pub use miner::{Candidate, Miner};
+
+mod params;
+pub use params::WorkParams;
+
+mod difficulty;
+pub use difficulty::{Difficulty, Target};
+
+mod aggregate;
+pub use aggregate::AggregateProof;
+
+mod calibrate;
+
 /// Basic result type.
 pub type Result<T> = std::result::Result<T, String>;
 
-/// Block count / memory usage.
+/// Default block count / memory usage, matching the original hardcoded
+/// parameters this crate shipped with.
 const BLOCK_COUNT: u32 = 16384;
 
-/// We only want to build a single instance of our parameters.
-static ARGON2: std::sync::LazyLock<argon2::Argon2<'static>> =
-    std::sync::LazyLock::new(|| {
-        let a = argon2::Argon2::new(
-            argon2::Algorithm::Argon2id,
-            argon2::Version::V0x13,
-            argon2::Params::new(BLOCK_COUNT, 1, 1, Some(16))
-                .expect("valid argon2 params"),
-        );
-        debug_assert_eq!(BLOCK_COUNT as usize, a.params().block_count());
-        a
-    });
+/// Number of output bytes read from Argon2 and interpreted as the
+/// difficulty number. Not (yet) configurable via [WorkParams].
+const OUTPUT_LEN: usize = 16;
 
 thread_local! {
     /// This is a decent amount of memory... Only allocate it once per
-    /// thread that needs to do the hashing.
+    /// thread that needs to do the hashing, and grow it on demand for
+    /// [WorkParams] that ask for more than the default memory cost.
     static MEM: std::cell::RefCell<Vec<argon2::Block>> =
         std::cell::RefCell::new(
             vec![argon2::Block::default(); BLOCK_COUNT as usize]
         );
 }
 
+/// Access the thread-local scratch memory, growing it if `params` needs
+/// more blocks than are currently allocated.
+fn with_mem<R>(
+    params: &WorkParams,
+    f: impl FnOnce(&mut [argon2::Block]) -> R,
+) -> R {
+    MEM.with_borrow_mut(|mem| {
+        let block_count = params.block_count();
+        if mem.len() < block_count {
+            mem.resize(block_count, argon2::Block::default());
+        }
+        f(&mut mem[..block_count])
+    })
+}
+
 /// 128 bit jitter
 const BIG_JITTER: &[u128] = &[
     232948893588309592072343451646495443470,
@@ -105,9 +167,13 @@ const SM_JITTER: &[u32] = &[
 /// - [WorkProof::verify] to check validity of a previously generated proof.
 #[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub struct WorkProof {
-    pwd: [u8; 20],
+    pwd: [u8; params::PWD_LEN],
     salt: [u8; 32],
-    difficulty: f64,
+    params: WorkParams,
+    secret: Option<Vec<u8>>,
+    associated_data: Option<Vec<u8>>,
+    out: u128,
+    difficulty: Difficulty,
     iter: std::num::Wrapping<u128>,
 }
 
@@ -115,14 +181,33 @@ pub struct WorkProof {
 impl WorkProof {
     /// New [WorkProof] instances for generating work proofs.
     ///
-    /// - count - the number of generator instances to produce
-    ///           for running parallel generation tasks.
-    /// - seed  - a pseudo random seed for starting the generation.
-    ///           this need not be cryptographically secure assuming
-    ///           the provided hash wash generated with a secure and
-    ///           well distributed hashing function.
-    /// - hash  - the hash to generate a proof against.
-    pub fn init(count: usize, seed: &[u8], hash: &[u8]) -> Result<Vec<Self>> {
+    /// - count  - the number of generator instances to produce
+    ///            for running parallel generation tasks.
+    /// - seed   - a pseudo random seed for starting the generation.
+    ///            this need not be cryptographically secure assuming
+    ///            the provided hash wash generated with a secure and
+    ///            well distributed hashing function.
+    /// - hash   - the hash to generate a proof against.
+    /// - params          - the Argon2 cost parameters to generate with.
+    ///                      Every resulting proof embeds these so a
+    ///                      verifier does not need to know them out of
+    ///                      band.
+    /// - secret           - an optional Argon2 secret key (`K`). A proof
+    ///                      generated with a secret can only be verified
+    ///                      by someone who also holds it, turning this
+    ///                      into a keyed PoW that is worthless against any
+    ///                      other validator.
+    /// - associated_data  - optional Argon2 associated data (`X`), e.g. a
+    ///                      domain string or expiry timestamp, binding the
+    ///                      proof to a context without touching the salt.
+    pub fn init(
+        count: usize,
+        seed: &[u8],
+        hash: &[u8],
+        params: WorkParams,
+        secret: Option<&[u8]>,
+        associated_data: Option<&[u8]>,
+    ) -> Result<Vec<Self>> {
         // check sizes of input data
         if seed.is_empty() || seed.len() > 20 {
             return Err("seed should be between 1 and 20 bytes".into());
@@ -133,7 +218,7 @@ impl WorkProof {
 
         let mut out = Vec::with_capacity(count);
 
-        let mut pwd = [0; 20];
+        let mut pwd = [0; params::PWD_LEN];
 
         // fill password with seed data
         pwd.copy_from_slice(
@@ -172,12 +257,17 @@ impl WorkProof {
             pwd[16..].copy_from_slice(&node.0.to_le_bytes());
 
             // get the starting difficulty
-            let difficulty = Self::verify(&pwd, &salt)?;
+            let (difficulty, raw) =
+                Self::hash_raw(&pwd, &salt, &params, secret, associated_data)?;
 
             // create the output item
             out.push(WorkProof {
                 pwd,
                 salt,
+                params,
+                secret: secret.map(<[u8]>::to_vec),
+                associated_data: associated_data.map(<[u8]>::to_vec),
+                out: raw,
                 difficulty,
                 iter,
             });
@@ -186,42 +276,123 @@ impl WorkProof {
         Ok(out)
     }
 
-    /// Verify a [WorkProof] against a provided hash. Returns a log10
-    /// difficulty.
-    pub fn verify(proof: &[u8], hash: &[u8]) -> Result<f64> {
-        let mut out = [0; 16];
-
-        // access thread memory
-        MEM.with_borrow_mut(|mem| {
-            // do the actual hashing
-            ARGON2.hash_password_into_with_memory(&proof, &hash, &mut out, mem)
+    /// Hash `pwd` against `salt` with `params` (and optional secret /
+    /// associated data), returning the log10 [Difficulty] and the raw
+    /// little-endian output value it was computed from. Shared by
+    /// [Self::next] (which already knows its own inputs) and
+    /// [Self::verify] (whose caller supplies the secret / associated
+    /// data alongside the encoded proof).
+    fn hash_raw(
+        pwd: &[u8; params::PWD_LEN],
+        salt: &[u8; 32],
+        params: &WorkParams,
+        secret: Option<&[u8]>,
+        associated_data: Option<&[u8]>,
+    ) -> Result<(Difficulty, u128)> {
+        let argon2 = params.build(secret, associated_data)?;
+
+        let mut out = [0; OUTPUT_LEN];
+        with_mem(params, |mem| {
+            argon2.hash_password_into_with_memory(pwd, salt, &mut out, mem)
         })
         .map_err(|err| err.to_string())?;
 
-        // calculate the log10 difficulty number
-        let pct = u128::from_le_bytes(out) as f64 / u128::MAX as f64;
-        let dif = (1.0 / (1.0 - pct)).log10();
+        // calculate the raw output value and its log10 difficulty, via the
+        // exact integer path in `Target::difficulty` -- the naive
+        // `(1.0 / (1.0 - pct)).log10()` collapses to `inf` well before the
+        // difficulty actually saturates (see difficulty.rs).
+        let mut padded = [0; 16];
+        padded[..OUTPUT_LEN].copy_from_slice(&out);
+        let raw = u128::from_le_bytes(padded);
+        let difficulty = Target::from_raw(raw).difficulty();
 
-        Ok(dif)
+        Ok((difficulty, raw))
     }
 
-    /// Iterate this [WorkProof]. Returns a log10 difficulty.
-    pub fn next(&mut self) -> Result<f64> {
+    /// Verify a self-describing, encoded proof (see [Self::proof]) against
+    /// a provided hash. `secret` and `associated_data` must match whatever
+    /// was passed to [Self::init] when the proof was generated. Returns the
+    /// achieved [Difficulty].
+    pub fn verify(
+        encoded_proof: &[u8],
+        hash: &[u8],
+        secret: Option<&[u8]>,
+        associated_data: Option<&[u8]>,
+    ) -> Result<Difficulty> {
+        if hash.len() != 32 {
+            return Err("hash must be 32 bytes".into());
+        }
+        let salt: [u8; 32] = hash.try_into().unwrap();
+
+        let (params, pwd) = WorkParams::decode(encoded_proof)?;
+        let (difficulty, _raw) =
+            Self::hash_raw(&pwd, &salt, &params, secret, associated_data)?;
+        Ok(difficulty)
+    }
+
+    /// Iterate this [WorkProof]. Returns the achieved [Difficulty].
+    pub fn next(&mut self) -> Result<Difficulty> {
         self.iter += 1;
         self.pwd[..16].copy_from_slice(&self.iter.0.to_le_bytes());
-        self.difficulty = Self::verify(&self.pwd, &self.salt)?;
+        let (difficulty, raw) = Self::hash_raw(
+            &self.pwd,
+            &self.salt,
+            &self.params,
+            self.secret.as_deref(),
+            self.associated_data.as_deref(),
+        )?;
+        self.out = raw;
+        self.difficulty = difficulty;
         Ok(self.difficulty)
     }
 
-    /// Get the current proof.
+    /// Get the current proof, encoded with the [WorkParams] it was
+    /// generated with so [Self::verify] can rebuild the exact Argon2
+    /// instance from the returned bytes alone.
     pub fn proof(&self) -> Vec<u8> {
-        self.pwd.to_vec()
+        self.params.encode(&self.pwd)
     }
 
-    /// Returns a log10 difficulty.
-    pub fn difficulty(&self) -> f64 {
+    /// Returns the achieved [Difficulty].
+    pub fn difficulty(&self) -> Difficulty {
         self.difficulty
     }
+
+    /// Cheap integer-only check of whether the current proof meets
+    /// `target`, without recomputing or comparing any floating point
+    /// difficulty. Intended for a miner's hot loop.
+    pub fn meets_target(&self, target: &Target) -> bool {
+        target.meets(self.out)
+    }
+
+    /// Force this proof's 4-byte node field, used by [AggregateProof] to
+    /// assign each of its solutions a distinct node id rather than the
+    /// randomized one [Self::init] picks for parallel workers.
+    pub(crate) fn set_node(&mut self, node: u32) {
+        self.pwd[16..].copy_from_slice(&node.to_le_bytes());
+    }
+
+    /// Recompute [Self::difficulty] (and the value [Self::meets_target]
+    /// checks) for the current `pwd`, without advancing [Self::next]'s
+    /// search counter. Used after [Self::set_node] changes `pwd` out from
+    /// under the difficulty computed by [Self::init].
+    pub(crate) fn refresh(&mut self) -> Result<Difficulty> {
+        let (difficulty, raw) = Self::hash_raw(
+            &self.pwd,
+            &self.salt,
+            &self.params,
+            self.secret.as_deref(),
+            self.associated_data.as_deref(),
+        )?;
+        self.out = raw;
+        self.difficulty = difficulty;
+        Ok(self.difficulty)
+    }
+
+    /// The raw, un-encoded 20-byte search value.
+    pub(crate) fn proof_bytes(&self) -> [u8; params::PWD_LEN] {
+        self.pwd
+    }
 }
 
 #[cfg(test)]
@@ -230,7 +401,15 @@ mod test {
 
     #[test]
     fn simple() {
-        let mut wp = WorkProof::init(2, &[0xdb; 20], &[0xdb; 32]).unwrap();
+        let mut wp = WorkProof::init(
+            2,
+            &[0xdb; 20],
+            &[0xdb; 32],
+            WorkParams::default(),
+            None,
+            None,
+        )
+        .unwrap();
         let mut wp1 = wp.remove(0);
         let mut wp2 = wp.remove(0);
 
@@ -240,17 +419,58 @@ mod test {
             (f * 1000.0) as u32
         }
 
-        data.push(fuzz(wp1.difficulty()));
-        data.push(fuzz(wp2.difficulty()));
+        data.push(fuzz(wp1.difficulty().to_f64()));
+        data.push(fuzz(wp2.difficulty().to_f64()));
 
         wp1.next().unwrap();
         wp2.next().unwrap();
 
-        data.push(fuzz(wp1.difficulty()));
-        data.push(fuzz(wp2.difficulty()));
+        data.push(fuzz(wp1.difficulty().to_f64()));
+        data.push(fuzz(wp2.difficulty().to_f64()));
 
         let exp: Vec<u32> = vec![148, 249, 705, 70];
 
         assert_eq!(exp, data);
     }
+
+    #[test]
+    fn keyed_proof_binds_to_secret_and_associated_data() {
+        let params = WorkParams {
+            mem_kib: 8,
+            iterations: 1,
+            lanes: 1,
+            version: argon2::Version::V0x13,
+        };
+        let hash = [0x11; 32];
+        let seed = [0x22; 20];
+        let secret = b"server-secret";
+        let associated_data = b"domain-context";
+
+        let wp = WorkProof::init(
+            1,
+            &seed,
+            &hash,
+            params,
+            Some(secret),
+            Some(associated_data),
+        )
+        .unwrap()
+        .remove(0);
+        let proof = wp.proof();
+
+        let difficulty =
+            WorkProof::verify(&proof, &hash, Some(secret), Some(associated_data))
+                .unwrap();
+        assert_eq!(difficulty, wp.difficulty());
+
+        let wrong_secret =
+            WorkProof::verify(&proof, &hash, Some(b"wrong-secret"), Some(associated_data))
+                .unwrap();
+        assert_ne!(difficulty, wrong_secret);
+
+        let wrong_ad =
+            WorkProof::verify(&proof, &hash, Some(secret), Some(b"wrong-context"))
+                .unwrap();
+        assert_ne!(difficulty, wrong_ad);
+    }
 }
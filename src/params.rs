@@ -0,0 +1,246 @@
+//! Configurable Argon2 cost parameters and the self-describing proof
+//! encoding built on top of them.
+
+/// Argon2id algorithm id used in the encoded proof header.
+const ALGO_ARGON2ID: u8 = 1;
+
+/// Encoding format version, independent of the Argon2 version byte.
+const FORMAT_VERSION: u8 = 1;
+
+/// `[algo_id][format_version][argon2_version][output_len][mem_kib:4][iterations:4][lanes:4]`
+const HEADER_LEN: usize = 16;
+
+/// Length of the raw search value (see the crate-level docs).
+pub(crate) const PWD_LEN: usize = 20;
+
+/// Total length of an encoded proof: header followed by the search value.
+pub(crate) const ENCODED_LEN: usize = HEADER_LEN + PWD_LEN;
+
+/// Largest `mem_kib` [WorkParams::decode] will accept from an untrusted
+/// proof. Without a ceiling, a malicious proof claiming e.g. `u32::MAX`
+/// KiB would force every verifier to allocate a multi-terabyte scratch
+/// buffer. 1 GiB.
+const MAX_DECODED_MEM_KIB: u32 = 1 << 20;
+
+/// Largest `iterations` [WorkParams::decode] will accept from an
+/// untrusted proof, so a malicious proof can't force unbounded
+/// verification CPU time.
+const MAX_DECODED_ITERATIONS: u32 = 64;
+
+/// Largest `lanes` [WorkParams::decode] will accept from an untrusted
+/// proof.
+const MAX_DECODED_LANES: u32 = 64;
+
+/// Tunable Argon2 cost parameters for a [WorkProof](crate::WorkProof).
+///
+/// These are the same m/t/p knobs any Argon2 tuning tool exposes, bundled
+/// up so a proof can be self-describing: [WorkParams::decode] recovers the
+/// exact settings used to generate a proof from the proof bytes alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkParams {
+    /// Memory cost, in KiB (argon2 "m").
+    pub mem_kib: u32,
+    /// Time cost / iteration count (argon2 "t").
+    pub iterations: u32,
+    /// Parallelism / lane count (argon2 "p").
+    pub lanes: u32,
+    /// Argon2 version to hash with.
+    pub version: argon2::Version,
+}
+
+impl Default for WorkParams {
+    /// The original hardcoded settings this crate shipped with.
+    fn default() -> Self {
+        WorkParams {
+            mem_kib: crate::BLOCK_COUNT,
+            iterations: 1,
+            lanes: 1,
+            version: argon2::Version::V0x13,
+        }
+    }
+}
+
+impl WorkParams {
+    /// Number of [argon2::Block]s `mem_kib` requires (1 block per KiB).
+    pub(crate) fn block_count(&self) -> usize {
+        self.mem_kib as usize
+    }
+
+    /// Decode an encoded `argon2::Version` byte, rejecting anything this
+    /// crate doesn't know how to build. Shared by [Self::decode] and
+    /// [crate::AggregateProof::decode], which both embed the same byte in
+    /// their headers.
+    pub(crate) fn decode_version(byte: u8) -> crate::Result<argon2::Version> {
+        match byte {
+            v if v == argon2::Version::V0x10 as u8 => Ok(argon2::Version::V0x10),
+            v if v == argon2::Version::V0x13 as u8 => Ok(argon2::Version::V0x13),
+            v => Err(format!("unsupported argon2 version {v}")),
+        }
+    }
+
+    /// Bound untrusted `mem_kib`/`iterations`/`lanes` decoded from a proof
+    /// before they ever reach `ParamsBuilder`/`with_mem`, or a malicious
+    /// proof could force a verifier to allocate unbounded scratch memory
+    /// or spend unbounded CPU time. Shared by [Self::decode] and
+    /// [crate::AggregateProof::decode].
+    pub(crate) fn validate_decoded_costs(
+        mem_kib: u32,
+        iterations: u32,
+        lanes: u32,
+    ) -> crate::Result<()> {
+        if mem_kib == 0 || mem_kib > MAX_DECODED_MEM_KIB {
+            return Err(format!(
+                "mem_kib {mem_kib} out of accepted range (1..={MAX_DECODED_MEM_KIB})"
+            ));
+        }
+        if iterations == 0 || iterations > MAX_DECODED_ITERATIONS {
+            return Err(format!(
+                "iterations {iterations} out of accepted range (1..={MAX_DECODED_ITERATIONS})"
+            ));
+        }
+        if lanes == 0 || lanes > MAX_DECODED_LANES {
+            return Err(format!(
+                "lanes {lanes} out of accepted range (1..={MAX_DECODED_LANES})"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Build the `argon2::Argon2` instance these params describe, optionally
+    /// keyed with a server secret and/or bound to associated data.
+    pub(crate) fn build<'k>(
+        &self,
+        secret: Option<&'k [u8]>,
+        associated_data: Option<&[u8]>,
+    ) -> crate::Result<argon2::Argon2<'k>> {
+        let mut builder = argon2::ParamsBuilder::new();
+        builder
+            .m_cost(self.mem_kib)
+            .t_cost(self.iterations)
+            .p_cost(self.lanes)
+            .output_len(crate::OUTPUT_LEN);
+        if let Some(associated_data) = associated_data {
+            let associated_data =
+                argon2::AssociatedData::new(associated_data)
+                    .map_err(|err| err.to_string())?;
+            builder.data(associated_data);
+        }
+        let params = builder.build().map_err(|err| err.to_string())?;
+
+        Ok(match secret {
+            Some(secret) => argon2::Argon2::new_with_secret(
+                secret,
+                argon2::Algorithm::Argon2id,
+                self.version,
+                params,
+            )
+            .map_err(|err| err.to_string())?,
+            None => argon2::Argon2::new(
+                argon2::Algorithm::Argon2id,
+                self.version,
+                params,
+            ),
+        })
+    }
+
+    /// Encode `pwd` and these params into a portable, self-describing proof.
+    pub(crate) fn encode(&self, pwd: &[u8; PWD_LEN]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(ENCODED_LEN);
+        out.push(ALGO_ARGON2ID);
+        out.push(FORMAT_VERSION);
+        out.push(self.version as u8);
+        out.push(crate::OUTPUT_LEN as u8);
+        out.extend_from_slice(&self.mem_kib.to_le_bytes());
+        out.extend_from_slice(&self.iterations.to_le_bytes());
+        out.extend_from_slice(&self.lanes.to_le_bytes());
+        out.extend_from_slice(pwd);
+        out
+    }
+
+    /// Decode an encoded proof back into the params used to generate it,
+    /// plus the raw 20-byte search value.
+    pub(crate) fn decode(
+        encoded: &[u8],
+    ) -> crate::Result<(Self, [u8; PWD_LEN])> {
+        if encoded.len() != ENCODED_LEN {
+            return Err(format!(
+                "encoded proof must be {ENCODED_LEN} bytes, got {}",
+                encoded.len()
+            ));
+        }
+        if encoded[0] != ALGO_ARGON2ID {
+            return Err(format!("unsupported algorithm id {}", encoded[0]));
+        }
+        if encoded[1] != FORMAT_VERSION {
+            return Err(format!("unsupported format version {}", encoded[1]));
+        }
+        let version = Self::decode_version(encoded[2])?;
+        let output_len = encoded[3] as usize;
+        if output_len != crate::OUTPUT_LEN {
+            return Err(format!(
+                "unsupported output length {output_len}, expected {}",
+                crate::OUTPUT_LEN
+            ));
+        }
+        let mem_kib = u32::from_le_bytes(encoded[4..8].try_into().unwrap());
+        let iterations =
+            u32::from_le_bytes(encoded[8..12].try_into().unwrap());
+        let lanes = u32::from_le_bytes(encoded[12..16].try_into().unwrap());
+
+        Self::validate_decoded_costs(mem_kib, iterations, lanes)?;
+
+        let mut pwd = [0; PWD_LEN];
+        pwd.copy_from_slice(&encoded[HEADER_LEN..]);
+
+        Ok((
+            WorkParams {
+                mem_kib,
+                iterations,
+                lanes,
+                version,
+            },
+            pwd,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_PARAMS: WorkParams = WorkParams {
+        mem_kib: 8,
+        iterations: 1,
+        lanes: 1,
+        version: argon2::Version::V0x13,
+    };
+
+    #[test]
+    fn encode_decode_roundtrip() {
+        let pwd = [7; PWD_LEN];
+        let encoded = TEST_PARAMS.encode(&pwd);
+        let (decoded, decoded_pwd) = WorkParams::decode(&encoded).unwrap();
+        assert_eq!(TEST_PARAMS, decoded);
+        assert_eq!(pwd, decoded_pwd);
+    }
+
+    #[test]
+    fn decode_rejects_oversized_mem_kib() {
+        let encoded = WorkParams {
+            mem_kib: u32::MAX,
+            ..TEST_PARAMS
+        }
+        .encode(&[0; PWD_LEN]);
+        assert!(WorkParams::decode(&encoded).is_err());
+    }
+
+    #[test]
+    fn decode_rejects_oversized_iterations() {
+        let encoded = WorkParams {
+            iterations: u32::MAX,
+            ..TEST_PARAMS
+        }
+        .encode(&[0; PWD_LEN]);
+        assert!(WorkParams::decode(&encoded).is_err());
+    }
+}